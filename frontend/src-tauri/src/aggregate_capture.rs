@@ -0,0 +1,719 @@
+use tauri::{AppHandle, Emitter};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{StreamConfig, SampleFormat, BufferSize};
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::audio_capture::{AudioDeviceLostEvent, AudioDeviceReconnectedEvent};
+use crate::resampler::Resampler;
+use crate::wav_recorder::WavRecorder;
+#[cfg(target_os = "windows")]
+use windows::Win32::Media::Audio::AUDCLNT_E_DEVICE_INVALIDATED;
+
+/// Mic and system audio are mixed at this rate regardless of their native
+/// device rates, same target the standalone captures resample to.
+pub const MIX_SAMPLE_RATE: u32 = 16000;
+/// 20ms worth of samples at `MIX_SAMPLE_RATE`; matches the resampler's own
+/// default emit cadence so the mixer drains frames as fast as either source
+/// can fill them.
+const MIX_FRAME_SAMPLES: usize = (MIX_SAMPLE_RATE as usize * 20) / 1000;
+
+/// Caps how far a source can get ahead of the mixer before its oldest
+/// samples are dropped to catch back up. Bounds both memory and the extra
+/// latency a source that's (even slightly) faster than the mixer drains it
+/// would otherwise build up without limit over a long session.
+const RING_BUFFER_CAP_SAMPLES: usize = MIX_SAMPLE_RATE as usize * 2;
+
+/// How long to wait before rebuilding against the new default device after
+/// a loss, so a flaky unplug/replug doesn't spin the rebuild loop. Matches
+/// `audio_capture::RECONNECT_BACKOFF`/`system_audio::RECONNECT_BACKOFF`.
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Sent from a source's stream error callback (or detected directly from a
+/// WASAPI error code on Windows) to its owning capture thread, or from
+/// `AggregateCapture::stop` to tear it down. Mirrors `audio_capture`'s
+/// private `CaptureControl`.
+enum CaptureControl {
+    Stop,
+    DeviceLost(String),
+}
+
+/// Per-source sample queue a capture thread pushes resampled mono `i16`
+/// samples into and the mixer thread drains from. Alignment between the two
+/// buffers comes from `AggregateCapture::start` releasing both sources to
+/// play at the same instant (see the build/go handshake there); the mixer
+/// then drains whichever buffer(s) have a frame ready, substituting silence
+/// for a source that's lagging so one underrunning source never stalls the
+/// other's live audio.
+struct RingBuffer {
+    samples: Mutex<VecDeque<i16>>,
+    cap: usize,
+}
+
+impl RingBuffer {
+    fn new(cap: usize) -> Self {
+        RingBuffer { samples: Mutex::new(VecDeque::new()), cap }
+    }
+
+    fn push(&self, samples: &[i16]) {
+        let mut buf = self.samples.lock().unwrap();
+        buf.extend(samples.iter().copied());
+        while buf.len() > self.cap {
+            buf.pop_front();
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    /// Pops exactly `len` samples, substituting silence for any the source
+    /// hasn't produced yet so one underrunning source never stalls the mix.
+    fn pop_frame(&self, len: usize) -> Vec<i16> {
+        let mut buf = self.samples.lock().unwrap();
+        (0..len).map(|_| buf.pop_front().unwrap_or(0)).collect()
+    }
+}
+
+/// Per-source gain applied before the two streams are summed.
+#[derive(Debug, Clone, Copy)]
+pub struct MixGains {
+    pub mic_gain: f32,
+    pub system_gain: f32,
+}
+
+impl Default for MixGains {
+    fn default() -> Self {
+        MixGains { mic_gain: 1.0, system_gain: 1.0 }
+    }
+}
+
+/// Runs the microphone stream and the system-audio stream simultaneously,
+/// each feeding its own [`RingBuffer`] through a resampler, and a mixer
+/// thread that pops aligned frames from both, sums them with per-source
+/// gain, and emits a single `mixed-audio-data` event.
+pub struct AggregateCapture {
+    _app_handle: AppHandle,
+    mic_control_tx: mpsc::Sender<CaptureControl>,
+    system_control_tx: mpsc::Sender<CaptureControl>,
+    mixer_stop_tx: mpsc::Sender<()>,
+    mic_worker: Option<JoinHandle<()>>,
+    system_worker: Option<JoinHandle<()>>,
+    mixer_worker: Option<JoinHandle<()>>,
+}
+
+impl AggregateCapture {
+    pub async fn start(
+        app_handle: AppHandle,
+        mic_device_name: Option<String>,
+        system_device_name: String,
+        gains: MixGains,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        println!("Starting aggregate capture (mic + system audio)...");
+
+        let mic_buffer = Arc::new(RingBuffer::new(RING_BUFFER_CAP_SAMPLES));
+        let system_buffer = Arc::new(RingBuffer::new(RING_BUFFER_CAP_SAMPLES));
+
+        let (mic_built_tx, mic_built_rx) = mpsc::channel::<Result<(), String>>();
+        let (mic_go_tx, mic_go_rx) = mpsc::channel::<()>();
+        let (mic_ready_tx, mic_ready_rx) = mpsc::channel::<Result<(), String>>();
+        let (mic_control_tx, mic_control_rx) = mpsc::channel::<CaptureControl>();
+        let mic_control_tx_clone = mic_control_tx.clone();
+        let mic_buffer_clone = mic_buffer.clone();
+        let mic_app_handle = app_handle.clone();
+        let mic_worker = thread::spawn(move || {
+            run_mic_capture(mic_app_handle, mic_device_name, mic_buffer_clone, mic_built_tx, mic_go_rx, mic_ready_tx, mic_control_tx_clone, mic_control_rx);
+        });
+
+        let (system_built_tx, system_built_rx) = mpsc::channel::<Result<(), String>>();
+        let (system_go_tx, system_go_rx) = mpsc::channel::<()>();
+        let (system_ready_tx, system_ready_rx) = mpsc::channel::<Result<(), String>>();
+        let (system_control_tx, system_control_rx) = mpsc::channel::<CaptureControl>();
+        let system_control_tx_clone = system_control_tx.clone();
+        let system_buffer_clone = system_buffer.clone();
+        let system_app_handle = app_handle.clone();
+        let system_worker = thread::spawn(move || {
+            run_system_capture(system_app_handle, system_device_name, system_buffer_clone, system_built_tx, system_go_rx, system_ready_tx, system_control_tx_clone, system_control_rx);
+        });
+
+        // Both sources build their stream concurrently but don't play() it
+        // yet; only once *both* report built do we release them together,
+        // so they start capturing from the same instant instead of whichever
+        // builds first gaining a head start on the other's ring buffer.
+        let mic_built = recv_ready(&mic_built_rx, "Microphone");
+        let system_built = recv_ready(&system_built_rx, "System audio");
+
+        if mic_built.is_ok() && system_built.is_ok() {
+            let _ = mic_go_tx.send(());
+            let _ = system_go_tx.send(());
+        }
+        // Dropping these (whether or not a go was sent above) unblocks
+        // whichever worker actually finished building in the failure case —
+        // its go_rx.recv() errors out and it exits without ever playing.
+        drop(mic_go_tx);
+        drop(system_go_tx);
+
+        if let Err(e) = mic_built {
+            let _ = mic_control_tx.send(CaptureControl::Stop);
+            let _ = mic_worker.join();
+            let _ = system_control_tx.send(CaptureControl::Stop);
+            let _ = system_worker.join();
+            return Err(e);
+        }
+        if let Err(e) = system_built {
+            let _ = mic_control_tx.send(CaptureControl::Stop);
+            let _ = mic_worker.join();
+            let _ = system_control_tx.send(CaptureControl::Stop);
+            let _ = system_worker.join();
+            return Err(e);
+        }
+
+        if let Err(e) = recv_ready(&mic_ready_rx, "Microphone") {
+            let _ = mic_control_tx.send(CaptureControl::Stop);
+            let _ = mic_worker.join();
+            let _ = system_control_tx.send(CaptureControl::Stop);
+            let _ = system_worker.join();
+            return Err(e);
+        }
+        if let Err(e) = recv_ready(&system_ready_rx, "System audio") {
+            let _ = mic_control_tx.send(CaptureControl::Stop);
+            let _ = mic_worker.join();
+            let _ = system_control_tx.send(CaptureControl::Stop);
+            let _ = system_worker.join();
+            return Err(e);
+        }
+
+        let (mixer_stop_tx, mixer_stop_rx) = mpsc::channel::<()>();
+        let app_handle_clone = app_handle.clone();
+        let mixer_worker = thread::spawn(move || {
+            run_mixer(app_handle_clone, mic_buffer, system_buffer, gains, recorder, mixer_stop_rx);
+        });
+
+        println!("Aggregate capture started successfully");
+
+        Ok(AggregateCapture {
+            _app_handle: app_handle,
+            mic_control_tx,
+            system_control_tx,
+            mixer_stop_tx,
+            mic_worker: Some(mic_worker),
+            system_worker: Some(system_worker),
+            mixer_worker: Some(mixer_worker),
+        })
+    }
+
+    pub async fn stop(mut self) {
+        let _ = self.mic_control_tx.send(CaptureControl::Stop);
+        let _ = self.system_control_tx.send(CaptureControl::Stop);
+        let _ = self.mixer_stop_tx.send(());
+
+        if let Some(worker) = self.mic_worker.take() {
+            let _ = worker.join();
+        }
+        if let Some(worker) = self.system_worker.take() {
+            let _ = worker.join();
+        }
+        if let Some(worker) = self.mixer_worker.take() {
+            let _ = worker.join();
+        }
+
+        println!("Aggregate capture stopped");
+    }
+}
+
+fn recv_ready(ready_rx: &mpsc::Receiver<Result<(), String>>, label: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(format!("{} capture thread exited before starting", label).into()),
+    }
+}
+
+/// Runs on the dedicated mic capture thread: builds and plays the stream,
+/// signals readiness via `built_tx`/`ready_tx`, then waits on `control_rx`.
+/// A `Stop` (or the sender dropping) ends the loop and drops the stream. A
+/// `DeviceLost` emits `audio-device-lost` and — so long as the caller asked
+/// for the default device rather than a named one — rebuilds the stream on
+/// whatever is the default now and emits `audio-device-reconnected`,
+/// without re-running the initial build/go handshake (the system-audio
+/// source keeps running uninterrupted while the mic reconnects).
+fn run_mic_capture(
+    app_handle: AppHandle,
+    device_name: Option<String>,
+    buffer: Arc<RingBuffer>,
+    built_tx: mpsc::Sender<Result<(), String>>,
+    go_rx: mpsc::Receiver<()>,
+    ready_tx: mpsc::Sender<Result<(), String>>,
+    control_tx: mpsc::Sender<CaptureControl>,
+    control_rx: mpsc::Receiver<CaptureControl>,
+) {
+    let follows_default_device = device_name.is_none();
+    let mut go_rx = Some(go_rx);
+    let mut first_attempt = true;
+
+    loop {
+        let stream = match build_mic_stream(device_name.clone(), buffer.clone(), control_tx.clone()) {
+            Ok(stream) => stream,
+            Err(e) => {
+                if first_attempt {
+                    let _ = built_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to rebuild aggregate mic stream after device loss: {}", e);
+                }
+                return;
+            }
+        };
+
+        if first_attempt {
+            let _ = built_tx.send(Ok(()));
+            // Wait until the system-audio source has also finished building
+            // before playing, so the two sources start from the same instant.
+            if go_rx.take().unwrap().recv().is_err() {
+                return;
+            }
+        }
+
+        if let Err(e) = stream.play() {
+            if first_attempt {
+                let _ = ready_tx.send(Err(e.to_string()));
+            } else {
+                eprintln!("Failed to restart aggregate mic stream after device loss: {}", e);
+            }
+            return;
+        }
+
+        if first_attempt {
+            let _ = ready_tx.send(Ok(()));
+            first_attempt = false;
+        } else {
+            println!("Aggregate mic device reconnected");
+            if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                device_name: "microphone".to_string(),
+            }) {
+                eprintln!("Failed to emit audio-device-reconnected: {}", e);
+            }
+        }
+
+        match control_rx.recv() {
+            Ok(CaptureControl::Stop) | Err(_) => {
+                // Dropping `stream` here pauses and releases the device.
+                return;
+            }
+            Ok(CaptureControl::DeviceLost(reason)) => {
+                drop(stream);
+                eprintln!("Aggregate mic device lost: {}", reason);
+                if let Err(e) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                    device_name: "microphone".to_string(),
+                    reason,
+                }) {
+                    eprintln!("Failed to emit audio-device-lost: {}", e);
+                }
+
+                if !follows_default_device {
+                    // A specific device was requested and it's gone; there's
+                    // no "default" to fall back to, so give up.
+                    return;
+                }
+
+                thread::sleep(RECONNECT_BACKOFF);
+                // Loop back around and rebuild against the current default.
+            }
+        }
+    }
+}
+
+fn build_mic_stream(
+    device_name: Option<String>,
+    buffer: Arc<RingBuffer>,
+    control_tx: mpsc::Sender<CaptureControl>,
+) -> Result<cpal::Stream, Box<dyn std::error::Error + Send + Sync>> {
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host.input_devices()?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format!("Microphone '{}' not found", name))?,
+        None => host.default_input_device().ok_or("No default input device available")?,
+    };
+
+    let default_config = device.default_input_config()?;
+    let sample_rate = default_config.sample_rate();
+    let channels = default_config.channels();
+    let stream_config = StreamConfig {
+        channels,
+        sample_rate,
+        buffer_size: BufferSize::Default,
+    };
+    let resampler = Mutex::new(Resampler::new(sample_rate.0, MIX_SAMPLE_RATE));
+
+    let stream = match default_config.sample_format() {
+        SampleFormat::F32 => {
+            let control_tx = control_tx.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let i16_data: Vec<i16> = data.iter()
+                        .map(|&sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+                        .collect();
+                    push_resampled(&resampler, &i16_data, channels, &buffer);
+                },
+                move |err| {
+                    eprintln!("Aggregate mic stream error: {}", err);
+                    let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
+                },
+                None,
+            )?
+        }
+        SampleFormat::I16 => {
+            let control_tx = control_tx.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                    push_resampled(&resampler, data, channels, &buffer);
+                },
+                move |err| {
+                    eprintln!("Aggregate mic stream error: {}", err);
+                    let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
+                },
+                None,
+            )?
+        }
+        SampleFormat::U16 => {
+            let control_tx = control_tx.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[u16], _: &cpal::InputCallbackInfo| {
+                    let i16_data: Vec<i16> = data.iter()
+                        .map(|&sample| (sample as i32 - 32768) as i16)
+                        .collect();
+                    push_resampled(&resampler, &i16_data, channels, &buffer);
+                },
+                move |err| {
+                    eprintln!("Aggregate mic stream error: {}", err);
+                    let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
+                },
+                None,
+            )?
+        }
+        _ => return Err("Unsupported sample format for aggregate microphone capture".into()),
+    };
+
+    Ok(stream)
+}
+
+fn push_resampled(resampler: &Mutex<Resampler>, data: &[i16], channels: u16, buffer: &RingBuffer) {
+    let chunks = resampler.lock().unwrap().process(data, channels);
+    for chunk in chunks {
+        buffer.push(&chunk);
+    }
+}
+
+// System-audio source for the aggregate mixer. macOS reuses the same
+// BlackHole-style virtual device cpal can open as an input; Windows reuses
+// the WASAPI loopback path. Other platforms have no native system-audio
+// source to mix in.
+
+#[cfg(target_os = "macos")]
+fn run_system_capture(
+    app_handle: AppHandle,
+    device_name: String,
+    buffer: Arc<RingBuffer>,
+    built_tx: mpsc::Sender<Result<(), String>>,
+    go_rx: mpsc::Receiver<()>,
+    ready_tx: mpsc::Sender<Result<(), String>>,
+    control_tx: mpsc::Sender<CaptureControl>,
+    control_rx: mpsc::Receiver<CaptureControl>,
+) {
+    let mut go_rx = Some(go_rx);
+    let mut first_attempt = true;
+
+    loop {
+        let stream = match build_macos_system_stream(device_name.clone(), buffer.clone(), control_tx.clone()) {
+            Ok(stream) => stream,
+            Err(e) => {
+                if first_attempt {
+                    let _ = built_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to rebuild aggregate system audio stream after device loss: {}", e);
+                }
+                return;
+            }
+        };
+
+        if first_attempt {
+            let _ = built_tx.send(Ok(()));
+            // Wait until the microphone source has also finished building
+            // before playing, so the two sources start from the same instant.
+            if go_rx.take().unwrap().recv().is_err() {
+                return;
+            }
+        }
+
+        if let Err(e) = stream.play() {
+            if first_attempt {
+                let _ = ready_tx.send(Err(e.to_string()));
+            } else {
+                eprintln!("Failed to restart aggregate system audio stream after device loss: {}", e);
+            }
+            return;
+        }
+
+        if first_attempt {
+            let _ = ready_tx.send(Ok(()));
+            first_attempt = false;
+        } else {
+            println!("Aggregate system audio device reconnected: {}", device_name);
+            if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                device_name: device_name.clone(),
+            }) {
+                eprintln!("Failed to emit audio-device-reconnected: {}", e);
+            }
+        }
+
+        match control_rx.recv() {
+            Ok(CaptureControl::Stop) | Err(_) => {
+                return;
+            }
+            Ok(CaptureControl::DeviceLost(reason)) => {
+                drop(stream);
+                eprintln!("Aggregate system audio device lost: {} ({})", device_name, reason);
+                if let Err(e) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                    device_name: device_name.clone(),
+                    reason,
+                }) {
+                    eprintln!("Failed to emit audio-device-lost: {}", e);
+                }
+
+                thread::sleep(RECONNECT_BACKOFF);
+                // Loop back around and retry against the same virtual
+                // device name (BlackHole/Soundflower reappear under the
+                // same name once reinstalled/reconnected).
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn build_macos_system_stream(
+    device_name: String,
+    buffer: Arc<RingBuffer>,
+    control_tx: mpsc::Sender<CaptureControl>,
+) -> Result<cpal::Stream, Box<dyn std::error::Error + Send + Sync>> {
+    let host = cpal::default_host();
+
+    let device = host.input_devices()?
+        .find(|d| d.name().map(|n| n.contains("BlackHole") || n.contains("Soundflower") || n == device_name).unwrap_or(false))
+        .ok_or_else(|| format!(
+            "System audio device '{}' not found. Install BlackHole or Soundflower for aggregate capture.",
+            device_name
+        ))?;
+
+    let default_config = device.default_input_config()?;
+    let sample_rate = default_config.sample_rate();
+    let channels = default_config.channels();
+    let stream_config = StreamConfig {
+        channels,
+        sample_rate,
+        buffer_size: BufferSize::Default,
+    };
+    let resampler = Mutex::new(Resampler::new(sample_rate.0, MIX_SAMPLE_RATE));
+
+    let stream = match default_config.sample_format() {
+        SampleFormat::F32 => {
+            let control_tx = control_tx.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let i16_data: Vec<i16> = data.iter()
+                        .map(|&sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+                        .collect();
+                    push_resampled(&resampler, &i16_data, channels, &buffer);
+                },
+                move |err| {
+                    eprintln!("Aggregate system audio stream error: {}", err);
+                    let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
+                },
+                None,
+            )?
+        }
+        SampleFormat::I16 => {
+            let control_tx = control_tx.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                    push_resampled(&resampler, data, channels, &buffer);
+                },
+                move |err| {
+                    eprintln!("Aggregate system audio stream error: {}", err);
+                    let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
+                },
+                None,
+            )?
+        }
+        _ => return Err("Unsupported sample format for aggregate system audio capture".into()),
+    };
+
+    Ok(stream)
+}
+
+#[cfg(target_os = "windows")]
+fn run_system_capture(
+    app_handle: AppHandle,
+    device_name: String,
+    buffer: Arc<RingBuffer>,
+    built_tx: mpsc::Sender<Result<(), String>>,
+    go_rx: mpsc::Receiver<()>,
+    ready_tx: mpsc::Sender<Result<(), String>>,
+    // WASAPI errors are detected directly from the packet-read result below
+    // rather than via a stream error callback, so this source never sends
+    // itself a `DeviceLost`; the parameter exists for signature parity with
+    // the macOS variant and so `AggregateCapture::stop`'s `Stop` still
+    // reaches this thread.
+    _control_tx: mpsc::Sender<CaptureControl>,
+    control_rx: mpsc::Receiver<CaptureControl>,
+) {
+    use crate::system_audio::{open_loopback_device, read_next_packet_mono};
+
+    let mut go_rx = Some(go_rx);
+    let mut first_attempt = true;
+
+    'outer: loop {
+        let (client, capture_client, channels, sample_rate) = match open_loopback_device(&device_name) {
+            Ok(parts) => parts,
+            Err(e) => {
+                if first_attempt {
+                    let _ = built_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to rebuild aggregate WASAPI loopback stream after device loss: {}", e);
+                }
+                return;
+            }
+        };
+
+        if first_attempt {
+            let _ = built_tx.send(Ok(()));
+            // Wait until the microphone source has also finished building
+            // before starting the client, so the two sources start from the
+            // same instant.
+            if go_rx.take().unwrap().recv().is_err() {
+                return;
+            }
+        }
+
+        if let Err(e) = unsafe { client.Start() } {
+            if first_attempt {
+                let _ = ready_tx.send(Err(e.to_string()));
+            } else {
+                eprintln!("Failed to restart aggregate WASAPI loopback stream after device loss: {}", e);
+            }
+            return;
+        }
+
+        if first_attempt {
+            let _ = ready_tx.send(Ok(()));
+            first_attempt = false;
+        } else {
+            println!("Aggregate system audio device reconnected: {}", device_name);
+            if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                device_name: device_name.clone(),
+            }) {
+                eprintln!("Failed to emit audio-device-reconnected: {}", e);
+            }
+        }
+
+        let resampler = Mutex::new(Resampler::new(sample_rate, MIX_SAMPLE_RATE));
+        loop {
+            match control_rx.try_recv() {
+                Ok(CaptureControl::Stop) | Err(mpsc::TryRecvError::Disconnected) => {
+                    let _ = unsafe { client.Stop() };
+                    return;
+                }
+                Ok(CaptureControl::DeviceLost(_)) | Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            match read_next_packet_mono(&capture_client, channels) {
+                Ok(Some(mono_data)) => push_resampled(&resampler, &mono_data, 1, &buffer),
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                Err(e) => {
+                    let _ = unsafe { client.Stop() };
+
+                    if e.code() == AUDCLNT_E_DEVICE_INVALIDATED {
+                        eprintln!("Aggregate system audio device lost: {} ({})", device_name, e);
+                        if let Err(emit_err) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                            device_name: device_name.clone(),
+                            reason: e.message().to_string(),
+                        }) {
+                            eprintln!("Failed to emit audio-device-lost: {}", emit_err);
+                        }
+                        thread::sleep(RECONNECT_BACKOFF);
+                        continue 'outer;
+                    }
+
+                    eprintln!("Aggregate WASAPI loopback error: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn run_system_capture(
+    _app_handle: AppHandle,
+    _device_name: String,
+    _buffer: Arc<RingBuffer>,
+    built_tx: mpsc::Sender<Result<(), String>>,
+    _go_rx: mpsc::Receiver<()>,
+    _ready_tx: mpsc::Sender<Result<(), String>>,
+    _control_tx: mpsc::Sender<CaptureControl>,
+    _control_rx: mpsc::Receiver<CaptureControl>,
+) {
+    let _ = built_tx.send(Err("Aggregate system-audio source is only available on macOS and Windows".to_string()));
+}
+
+fn run_mixer(
+    app_handle: AppHandle,
+    mic_buffer: Arc<RingBuffer>,
+    system_buffer: Arc<RingBuffer>,
+    gains: MixGains,
+    recorder: Arc<Mutex<Option<WavRecorder>>>,
+    stop_rx: mpsc::Receiver<()>,
+) {
+    // Only polled while neither buffer has a frame ready; as soon as either
+    // does, the loop drains a frame from both (substituting silence for
+    // whichever one is short) so a source that's lagging or briefly idle —
+    // e.g. WASAPI loopback delivering nothing while the render endpoint is
+    // silent — never stalls the other source's live audio.
+    let poll_interval = std::time::Duration::from_millis(5);
+
+    while stop_rx.try_recv().is_err() {
+        if mic_buffer.len() < MIX_FRAME_SAMPLES && system_buffer.len() < MIX_FRAME_SAMPLES {
+            thread::sleep(poll_interval);
+            continue;
+        }
+
+        let mic_frame = mic_buffer.pop_frame(MIX_FRAME_SAMPLES);
+        let system_frame = system_buffer.pop_frame(MIX_FRAME_SAMPLES);
+
+        let mixed: Vec<i16> = mic_frame.iter().zip(system_frame.iter())
+            .map(|(&mic, &sys)| {
+                let combined = mic as f32 * gains.mic_gain + sys as f32 * gains.system_gain;
+                combined.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+            })
+            .collect();
+
+        if let Some(rec) = recorder.lock().unwrap().as_ref() {
+            rec.push_samples(&mixed);
+        }
+
+        let audio_bytes = mixed.iter()
+            .flat_map(|&sample| sample.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        if let Err(e) = app_handle.emit("mixed-audio-data", audio_bytes) {
+            eprintln!("Failed to emit mixed audio data: {}", e);
+        }
+    }
+}
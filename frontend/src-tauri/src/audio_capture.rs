@@ -1,36 +1,277 @@
 use tauri::{AppHandle, Emitter};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{StreamConfig, SampleFormat, BufferSize, SampleRate};
+use cpal::{Device, StreamConfig, SampleFormat, BufferSize, SampleRate};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 
+use crate::resampler::Resampler;
+use crate::wav_recorder::WavRecorder;
+
+/// Consumers of the `audio-data` event (speech/transcription) expect a
+/// single fixed rate regardless of what the capture device natively runs
+/// at, so this is what we resample to unless a caller asks for another.
+const DEFAULT_TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// How long to wait before retrying after a device is lost, so a flaky
+/// unplug/replug doesn't spin the rebuild loop.
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AudioDevice {
     pub name: String,
     pub device_type: String, // "input" or "output"
     pub is_default: bool,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceLostEvent {
+    pub device_name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceReconnectedEvent {
+    pub device_name: String,
+}
+
+/// Emitted when a reconnect lands on a different native sample rate or
+/// channel count than the WAV header an in-progress recording was opened
+/// with. The recorder is stopped rather than left open so the file on disk
+/// stays valid instead of silently filling with samples its header no
+/// longer describes.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInterruptedEvent {
+    pub source: String,
+    pub reason: String,
+}
+
+/// Stops and finalizes `recorder`'s in-progress recording when a reconnect
+/// lands on a different sample rate/channel count than what its WAV header
+/// was opened with, emitting `recording-interrupted` instead of silently
+/// tee'ing mismatched samples into a now-wrong header. No-op if the spec
+/// didn't actually change or no recording is in progress. Shared by
+/// `AudioCapture` and `SystemAudioCapture`'s reconnect paths.
+pub(crate) fn stop_recorder_on_spec_mismatch(
+    app_handle: &AppHandle,
+    source: &str,
+    recorder: &Mutex<Option<WavRecorder>>,
+    previous_spec: (u32, u16),
+    new_spec: (u32, u16),
+) {
+    if previous_spec == new_spec {
+        return;
+    }
+    let stopped = recorder.lock().unwrap().take();
+    if let Some(rec) = stopped {
+        let reason = format!(
+            "device reconnected at {}Hz/{}ch (was {}Hz/{}ch); recording stopped to avoid a mismatched WAV header",
+            new_spec.0, new_spec.1, previous_spec.0, previous_spec.1
+        );
+        if let Err(e) = rec.stop() {
+            eprintln!("Failed to finalize {} recording after reconnect: {}", source, e);
+        }
+        println!("{} recording stopped: {}", source, reason);
+        if let Err(e) = app_handle.emit("recording-interrupted", RecordingInterruptedEvent {
+            source: source.to_string(),
+            reason,
+        }) {
+            eprintln!("Failed to emit recording-interrupted: {}", e);
+        }
+    }
+}
+
+/// Sent from the stream's error callback to the owning capture thread, or
+/// from `stop()` to tear it down.
+enum CaptureControl {
+    Stop,
+    DeviceLost(String),
 }
 
+/// Owns the capture thread that keeps the `cpal::Stream` alive.
+///
+/// `cpal::Stream` is `!Send`, so it can't be stored directly on this struct;
+/// instead it lives on the stack of a dedicated thread that parks on
+/// `control_rx` until told to shut down. Dropping the stream (by letting the
+/// thread return) actually stops capture, unlike `std::mem::forget`.
+///
+/// That same thread also supervises the stream: a fatal stream error (e.g.
+/// a USB mic unplugged, or the default device changing) is reported back
+/// via `CaptureControl::DeviceLost`, which emits `audio-device-lost` and,
+/// if the caller didn't pin a specific device, transparently rebuilds the
+/// stream on the current default device and emits `audio-device-reconnected`.
 pub struct AudioCapture {
     _app_handle: AppHandle,
-    // We'll use a simpler approach - let the stream live independently
+    control_tx: mpsc::Sender<CaptureControl>,
+    worker: Option<JoinHandle<()>>,
+    /// The native sample rate and channel count the stream currently has
+    /// open, so `start_recording` can build a WAV header that matches
+    /// what's actually being tee'd into [`recorder`](Self) — not the
+    /// resampled `audio-data` rate. Shared with the capture thread so a
+    /// reconnect that lands on a different rate (a replacement device isn't
+    /// guaranteed to match the original) is reflected here too, instead of
+    /// silently leaving a stale spec behind for an in-progress recording.
+    spec: Arc<Mutex<(u32, u16)>>,
 }
 
 impl AudioCapture {
     #[allow(dead_code)]
     pub async fn new(app_handle: AppHandle) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        Self::new_with_device(app_handle, None).await
+        Self::new_with_device(app_handle, None, None, None, Arc::new(Mutex::new(None))).await
     }
-    
-    pub async fn new_with_device(app_handle: AppHandle, device_name: Option<String>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+
+    pub async fn new_with_device(
+        app_handle: AppHandle,
+        device_name: Option<String>,
+        target_sample_rate: Option<u32>,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         println!("Starting system audio capture...");
-        
-        let host = cpal::default_host();
-        
-        // Select device based on name or use default
-        let device = if let Some(name) = device_name {
+
+        let target_sample_rate = target_sample_rate.unwrap_or(DEFAULT_TARGET_SAMPLE_RATE);
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        let (control_tx, control_rx) = mpsc::channel::<CaptureControl>();
+        let control_tx_clone = control_tx.clone();
+        let app_handle_clone = app_handle.clone();
+        let spec = Arc::new(Mutex::new((0, 0)));
+        let spec_clone = spec.clone();
+
+        let worker = thread::spawn(move || {
+            Self::run_capture(app_handle_clone, device_name, target_sample_rate, host_name, recorder, spec_clone, ready_tx, control_tx_clone, control_rx);
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Err("Audio capture thread exited before starting".into()),
+        }
+
+        println!("System audio capture started successfully");
+
+        Ok(AudioCapture {
+            _app_handle: app_handle,
+            control_tx,
+            worker: Some(worker),
+            spec,
+        })
+    }
+
+    /// The native sample rate the stream currently has open. May change
+    /// across a reconnect.
+    pub fn sample_rate(&self) -> u32 {
+        self.spec.lock().unwrap().0
+    }
+
+    /// The native channel count the stream currently has open. May change
+    /// across a reconnect.
+    pub fn channels(&self) -> u16 {
+        self.spec.lock().unwrap().1
+    }
+
+    /// Runs on the dedicated capture thread: builds and plays the stream,
+    /// signals readiness via `ready_tx`, then waits on `control_rx`. A
+    /// `Stop` (or the sender dropping) ends the loop and drops the stream.
+    /// A `DeviceLost` emits `audio-device-lost`, and — so long as the
+    /// caller asked for the default device rather than a named one —
+    /// rebuilds the stream on whatever is the default now and emits
+    /// `audio-device-reconnected`.
+    fn run_capture(
+        app_handle: AppHandle,
+        device_name: Option<String>,
+        target_sample_rate: u32,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+        spec: Arc<Mutex<(u32, u16)>>,
+        ready_tx: mpsc::Sender<Result<(), String>>,
+        control_tx: mpsc::Sender<CaptureControl>,
+        control_rx: mpsc::Receiver<CaptureControl>,
+    ) {
+        let follows_default_device = device_name.is_none();
+        let mut first_attempt = true;
+
+        loop {
+            let build_result = Self::build_stream(app_handle.clone(), device_name.clone(), target_sample_rate, host_name.clone(), recorder.clone(), control_tx.clone());
+            let (stream, device_label, sample_rate, channels) = match build_result {
+                Ok(built) => built,
+                Err(e) => {
+                    if first_attempt {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                    } else {
+                        eprintln!("Failed to rebuild audio stream after device loss: {}", e);
+                    }
+                    return;
+                }
+            };
+
+            if let Err(e) = stream.play() {
+                if first_attempt {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to restart audio stream after device loss: {}", e);
+                }
+                return;
+            }
+
+            // Update the shared spec on every successful (re)build — a
+            // reconnect's replacement device isn't guaranteed to reopen at
+            // the same rate/channels, and a WAV header already written for
+            // an in-progress recording can't be changed after the fact, so
+            // stop the recording rather than silently tee'ing mismatched
+            // samples under the old header.
+            let previous_spec = *spec.lock().unwrap();
+            *spec.lock().unwrap() = (sample_rate, channels);
+            if !first_attempt {
+                stop_recorder_on_spec_mismatch(&app_handle, "mic", &recorder, previous_spec, (sample_rate, channels));
+            }
+
+            if first_attempt {
+                let _ = ready_tx.send(Ok(()));
+                first_attempt = false;
+            } else {
+                println!("Audio device reconnected: {}", device_label);
+                if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                    device_name: device_label.clone(),
+                }) {
+                    eprintln!("Failed to emit audio-device-reconnected: {}", e);
+                }
+            }
+
+            match control_rx.recv() {
+                Ok(CaptureControl::Stop) | Err(_) => {
+                    // Dropping `stream` here pauses and releases the device.
+                    return;
+                }
+                Ok(CaptureControl::DeviceLost(reason)) => {
+                    drop(stream);
+                    eprintln!("Audio device lost: {} ({})", device_label, reason);
+                    if let Err(e) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                        device_name: device_label,
+                        reason,
+                    }) {
+                        eprintln!("Failed to emit audio-device-lost: {}", e);
+                    }
+
+                    if !follows_default_device {
+                        // A specific device was requested and it's gone; there's
+                        // no "default" to fall back to, so give up.
+                        return;
+                    }
+
+                    thread::sleep(RECONNECT_BACKOFF);
+                    // Loop back around and rebuild against the current default.
+                }
+            }
+        }
+    }
+
+    fn select_device(host: &cpal::Host, device_name: Option<String>) -> Result<Device, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(name) = device_name {
             // Try to find device by name
             let mut selected_device = None;
-            
+
             // Check input devices first
             for device in host.input_devices()? {
                 if let Ok(device_name_str) = device.name() {
@@ -40,7 +281,7 @@ impl AudioCapture {
                     }
                 }
             }
-            
+
             // If not found in input devices, check output devices for system audio capture
             if selected_device.is_none() {
                 for device in host.output_devices()? {
@@ -52,20 +293,33 @@ impl AudioCapture {
                     }
                 }
             }
-            
-            selected_device.ok_or(format!("Device '{}' not found", name))?
+
+            selected_device.ok_or(format!("Device '{}' not found", name).into())
         } else {
             // Use default input device
             host.default_input_device()
-                .ok_or("No default input device available")?
-        };
-        
-        println!("Using audio device: {}", device.name()?);
-        
+                .ok_or("No default input device available".into())
+        }
+    }
+
+    fn build_stream(
+        app_handle: AppHandle,
+        device_name: Option<String>,
+        target_sample_rate: u32,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+        control_tx: mpsc::Sender<CaptureControl>,
+    ) -> Result<(cpal::Stream, String, u32, u16), Box<dyn std::error::Error + Send + Sync>> {
+        let host = resolve_host(host_name.as_deref())?;
+        let device = Self::select_device(&host, device_name)?;
+
+        let device_label = device.name()?;
+        println!("Using audio device: {}", device_label);
+
         // Get default config and adapt to device capabilities
         let default_config = device.default_input_config()?;
         println!("Default input config: {:?}", default_config);
-        
+
         // Try to use device's native sample rate if available, otherwise fallback to common rates
         let sample_rate = if default_config.sample_rate().0 >= 16000 {
             // Use device's native rate if it's >= 16kHz
@@ -74,28 +328,31 @@ impl AudioCapture {
             // Try common sample rates
             SampleRate(44100) // Most common fallback
         };
-        
+
         // Use mono if possible, otherwise use device's default channels
         let channels = if default_config.channels() >= 1 {
             1 // Prefer mono for speech
         } else {
             default_config.channels()
         };
-        
+
         let stream_config = StreamConfig {
             channels,
             sample_rate,
             buffer_size: BufferSize::Default, // Use device's preferred buffer size
         };
-        
-        println!("Using stream config: channels={}, sample_rate={}, buffer_size=Default", 
-                 channels, sample_rate.0);
-        
+
+        println!("Using stream config: channels={}, sample_rate={}, buffer_size=Default, target_sample_rate={}",
+                 channels, sample_rate.0, target_sample_rate);
+
         let app_handle_clone = app_handle.clone();
-        
+        let resampler = std::sync::Mutex::new(Resampler::new(sample_rate.0, target_sample_rate));
+
         // Create stream based on sample format
         let stream = match default_config.sample_format() {
             SampleFormat::F32 => {
+                let control_tx = control_tx.clone();
+                let recorder = recorder.clone();
                 device.build_input_stream(
                     &stream_config,
                     move |data: &[f32], _: &cpal::InputCallbackInfo| {
@@ -103,42 +360,36 @@ impl AudioCapture {
                         let i16_data: Vec<i16> = data.iter()
                             .map(|&sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
                             .collect();
-                        
-                        let audio_bytes = i16_data.iter()
-                            .flat_map(|&sample| sample.to_le_bytes())
-                            .collect::<Vec<u8>>();
-                        
-                        // Emit audio data to frontend
-                        if let Err(e) = app_handle_clone.emit("audio-data", audio_bytes) {
-                            eprintln!("Failed to emit audio data: {}", e);
-                        }
+
+                        tee_to_recorder(&recorder, &i16_data);
+                        emit_resampled(&app_handle_clone, &resampler, &i16_data, channels);
                     },
                     move |err| {
                         eprintln!("Audio stream error: {}", err);
+                        let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
                     },
                     None,
                 )?
             }
             SampleFormat::I16 => {
+                let control_tx = control_tx.clone();
+                let recorder = recorder.clone();
                 device.build_input_stream(
                     &stream_config,
                     move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        let audio_bytes = data.iter()
-                            .flat_map(|&sample| sample.to_le_bytes())
-                            .collect::<Vec<u8>>();
-                        
-                        // Emit audio data to frontend
-                        if let Err(e) = app_handle_clone.emit("audio-data", audio_bytes) {
-                            eprintln!("Failed to emit audio data: {}", e);
-                        }
+                        tee_to_recorder(&recorder, data);
+                        emit_resampled(&app_handle_clone, &resampler, data, channels);
                     },
                     move |err| {
                         eprintln!("Audio stream error: {}", err);
+                        let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
                     },
                     None,
                 )?
             }
             SampleFormat::U16 => {
+                let control_tx = control_tx.clone();
+                let recorder = recorder.clone();
                 device.build_input_stream(
                     &stream_config,
                     move |data: &[u16], _: &cpal::InputCallbackInfo| {
@@ -146,18 +397,13 @@ impl AudioCapture {
                         let i16_data: Vec<i16> = data.iter()
                             .map(|&sample| (sample as i32 - 32768) as i16)
                             .collect();
-                        
-                        let audio_bytes = i16_data.iter()
-                            .flat_map(|&sample| sample.to_le_bytes())
-                            .collect::<Vec<u8>>();
-                        
-                        // Emit audio data to frontend
-                        if let Err(e) = app_handle_clone.emit("audio-data", audio_bytes) {
-                            eprintln!("Failed to emit audio data: {}", e);
-                        }
+
+                        tee_to_recorder(&recorder, &i16_data);
+                        emit_resampled(&app_handle_clone, &resampler, &i16_data, channels);
                     },
                     move |err| {
                         eprintln!("Audio stream error: {}", err);
+                        let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
                     },
                     None,
                 )?
@@ -166,69 +412,118 @@ impl AudioCapture {
                 return Err("Unsupported sample format".into());
             }
         };
-        
-        // Start the stream
-        stream.play()?;
-        println!("System audio capture started successfully");
-        
-        // Let the stream live independently
-        std::mem::forget(stream); // Keep the stream alive
-        
-        let capture = AudioCapture {
-            _app_handle: app_handle,
-        };
-        
-        Ok(capture)
+
+        Ok((stream, device_label, sample_rate.0, channels))
     }
-    
-    pub async fn stop(self) {
-        // Note: We can't easily stop the stream due to Rust's ownership system
-        // In a production app, we'd use a more sophisticated approach
-        println!("System audio capture stopped (stream continues running)");
+
+    pub async fn stop(mut self) {
+        // Signal the capture thread to drop the stream, then wait for it to
+        // actually exit so the device is released before we return.
+        let _ = self.control_tx.send(CaptureControl::Stop);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        println!("System audio capture stopped");
     }
-    
-    pub async fn list_devices() -> Result<Vec<AudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
-        let host = cpal::default_host();
+
+    pub async fn list_devices(host_name: Option<String>) -> Result<Vec<AudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
+        let host = resolve_host(host_name.as_deref())?;
+        let host_label = host.id().name().to_string();
         let mut devices = Vec::new();
-        
+
         // Get default devices for comparison
         let default_input = host.default_input_device();
         let default_output = host.default_output_device();
-        
+
         // List input devices (microphones)
         for device in host.input_devices()? {
             match device.name() {
                 Ok(name) => {
                     let is_default = default_input.as_ref()
                         .map_or(false, |d| d.name().map_or(false, |n| n == name));
-                    
+
                     devices.push(AudioDevice {
                         name: name.clone(),
                         device_type: "input".to_string(),
                         is_default,
+                        host: host_label.clone(),
                     });
                 }
                 Err(e) => eprintln!("Error getting input device name: {}", e),
             }
         }
-        
+
         // List output devices (speakers/system audio)
         for device in host.output_devices()? {
             match device.name() {
                 Ok(name) => {
                     let is_default = default_output.as_ref()
                         .map_or(false, |d| d.name().map_or(false, |n| n == name));
-                    
+
                     devices.push(AudioDevice {
                         name: name.clone(),
                         device_type: "output".to_string(),
                         is_default,
+                        host: host_label.clone(),
                     });
                 }
                 Err(e) => eprintln!("Error getting output device name: {}", e),
             }
         }
-        
+
         Ok(devices)
     }
-}
\ No newline at end of file
+}
+
+/// Resolves a host by the name reported in `list_audio_hosts`, falling back
+/// to `cpal::default_host()` when none is given. This is how the mic capture,
+/// the macOS system-audio capture and the device listings reach a
+/// non-default host like JACK or ASIO instead of always using whatever cpal
+/// considers the default. The aggregate capture does not use it and always
+/// opens its sources on `cpal::default_host()`.
+pub(crate) fn resolve_host(host_name: Option<&str>) -> Result<cpal::Host, Box<dyn std::error::Error + Send + Sync>> {
+    match host_name {
+        Some(name) => {
+            let host_id = cpal::available_hosts()
+                .into_iter()
+                .find(|id| id.name() == name)
+                .ok_or_else(|| format!("Audio host '{}' not found", name))?;
+            Ok(cpal::host_from_id(host_id)?)
+        }
+        None => Ok(cpal::default_host()),
+    }
+}
+
+/// Lists the audio hosts cpal knows how to talk to on this platform (e.g.
+/// CoreAudio on macOS, WASAPI/ASIO on Windows, ALSA/JACK/PulseAudio on
+/// Linux), for the `list_audio_hosts` Tauri command.
+pub async fn list_hosts() -> Vec<String> {
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| id.name().to_string())
+        .collect()
+}
+
+/// Forwards a captured block to the WAV recorder, if one is currently
+/// attached, before it's resampled for the `audio-data` event — the
+/// recording keeps the stream's native rate and channel count.
+fn tee_to_recorder(recorder: &Mutex<Option<WavRecorder>>, data: &[i16]) {
+    if let Some(rec) = recorder.lock().unwrap().as_ref() {
+        rec.push_samples(data);
+    }
+}
+
+/// Resamples a captured block and emits each fixed-size chunk the
+/// resampler hands back as a separate `audio-data` event.
+fn emit_resampled(app_handle: &AppHandle, resampler: &std::sync::Mutex<Resampler>, data: &[i16], channels: u16) {
+    let chunks = resampler.lock().unwrap().process(data, channels);
+    for chunk in chunks {
+        let audio_bytes = chunk.iter()
+            .flat_map(|&sample| sample.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        if let Err(e) = app_handle.emit("audio-data", audio_bytes) {
+            eprintln!("Failed to emit audio data: {}", e);
+        }
+    }
+}
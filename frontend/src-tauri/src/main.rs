@@ -5,15 +5,27 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 // Audio capture module
+mod aggregate_capture;
 mod audio_capture;
+mod resampler;
 mod system_audio;
+mod wav_recorder;
+use aggregate_capture::{AggregateCapture, MixGains};
 use audio_capture::{AudioCapture, AudioDevice};
 use system_audio::{SystemAudioCapture, SystemAudioDevice};
+use wav_recorder::WavRecorder;
 
 // Shared state for audio capture
 struct AppState {
     audio_capture: Arc<Mutex<Option<AudioCapture>>>,
     system_audio_capture: Arc<Mutex<Option<SystemAudioCapture>>>,
+    aggregate_capture: Arc<Mutex<Option<AggregateCapture>>>,
+    // Recording slots, independent of whether the corresponding capture is
+    // running yet: `start_recording` attaches a `WavRecorder` here and the
+    // capture callbacks tee into it whenever one is present.
+    mic_recorder: Arc<Mutex<Option<WavRecorder>>>,
+    system_recorder: Arc<Mutex<Option<WavRecorder>>>,
+    mixed_recorder: Arc<Mutex<Option<WavRecorder>>>,
 }
 
 // Tauri command to start system audio capture
@@ -22,17 +34,19 @@ async fn start_system_audio_capture(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
     device_name: Option<String>,
+    target_sample_rate: Option<u32>,
+    host: Option<String>,
 ) -> Result<(), String> {
     println!("Starting system audio capture...");
-    
+
     {
         let audio_capture = state.audio_capture.lock().unwrap();
         if audio_capture.is_some() {
             return Err("Audio capture already running".to_string());
         }
     }
-    
-    match AudioCapture::new_with_device(app_handle, device_name).await {
+
+    match AudioCapture::new_with_device(app_handle, device_name, target_sample_rate, host, state.mic_recorder.clone()).await {
         Ok(capture) => {
             let mut audio_capture = state.audio_capture.lock().unwrap();
             *audio_capture = Some(capture);
@@ -69,30 +83,38 @@ async fn stop_system_audio_capture(
 
 // Tauri command to list available audio devices
 #[tauri::command]
-async fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
-    match AudioCapture::list_devices().await {
+async fn list_audio_devices(host: Option<String>) -> Result<Vec<AudioDevice>, String> {
+    match AudioCapture::list_devices(host).await {
         Ok(devices) => Ok(devices),
         Err(e) => Err(format!("Failed to list audio devices: {}", e))
     }
 }
 
+// Tauri command to list the audio hosts cpal can enumerate devices on for
+// this platform (e.g. CoreAudio, WASAPI, ASIO, ALSA, JACK, PulseAudio).
+#[tauri::command]
+async fn list_audio_hosts() -> Vec<String> {
+    audio_capture::list_hosts().await
+}
+
 // Tauri command to start system audio capture specifically
 #[tauri::command]
 async fn start_system_audio_capture_device(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
     device_name: String,
+    host: Option<String>,
 ) -> Result<(), String> {
     println!("Starting system audio capture for device: {}", device_name);
-    
+
     {
         let system_audio_capture = state.system_audio_capture.lock().unwrap();
         if system_audio_capture.is_some() {
             return Err("System audio capture already running".to_string());
         }
     }
-    
-    match SystemAudioCapture::new_with_device(app_handle, device_name).await {
+
+    match SystemAudioCapture::new_with_device(app_handle, device_name, host, state.system_recorder.clone()).await {
         Ok(capture) => {
             let mut system_audio_capture = state.system_audio_capture.lock().unwrap();
             *system_audio_capture = Some(capture);
@@ -118,7 +140,8 @@ async fn stop_system_audio_capture_device(
         system_audio_capture.take()
     };
     
-    if let Some(_capture) = capture {
+    if let Some(capture) = capture {
+        capture.stop().await;
         println!("System audio capture stopped");
         Ok(())
     } else {
@@ -128,18 +151,165 @@ async fn stop_system_audio_capture_device(
 
 // Tauri command to list system audio devices
 #[tauri::command]
-async fn list_system_audio_devices() -> Result<Vec<SystemAudioDevice>, String> {
-    match SystemAudioCapture::list_system_audio_devices().await {
+async fn list_system_audio_devices(host: Option<String>) -> Result<Vec<SystemAudioDevice>, String> {
+    match SystemAudioCapture::list_system_audio_devices(host).await {
         Ok(devices) => Ok(devices),
         Err(e) => Err(format!("Failed to list system audio devices: {}", e))
     }
 }
 
+// Tauri command to start aggregate capture: mic + system audio mixed into
+// a single `mixed-audio-data` stream, for meeting-transcription use cases
+// that need both sides of the conversation on one timeline.
+#[tauri::command]
+async fn start_aggregate_capture(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    mic_device_name: Option<String>,
+    system_device_name: String,
+    mic_gain: Option<f32>,
+    system_gain: Option<f32>,
+) -> Result<(), String> {
+    println!("Starting aggregate capture...");
+
+    {
+        let aggregate_capture = state.aggregate_capture.lock().unwrap();
+        if aggregate_capture.is_some() {
+            return Err("Aggregate capture already running".to_string());
+        }
+    }
+
+    let gains = MixGains {
+        mic_gain: mic_gain.unwrap_or(1.0),
+        system_gain: system_gain.unwrap_or(1.0),
+    };
+
+    match AggregateCapture::start(app_handle, mic_device_name, system_device_name, gains, state.mixed_recorder.clone()).await {
+        Ok(capture) => {
+            let mut aggregate_capture = state.aggregate_capture.lock().unwrap();
+            *aggregate_capture = Some(capture);
+            println!("Aggregate capture started successfully");
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to start aggregate capture: {}", e);
+            Err(format!("Failed to start aggregate capture: {}", e))
+        }
+    }
+}
+
+// Tauri command to stop aggregate capture
+#[tauri::command]
+async fn stop_aggregate_capture(
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Stopping aggregate capture...");
+
+    let capture = {
+        let mut aggregate_capture = state.aggregate_capture.lock().unwrap();
+        aggregate_capture.take()
+    };
+
+    if let Some(capture) = capture {
+        capture.stop().await;
+        println!("Aggregate capture stopped");
+        Ok(())
+    } else {
+        Err("No aggregate capture running".to_string())
+    }
+}
+
+// Which capture stream `start_recording`/`stop_recording` should tee from.
+// The mixed source always runs at `aggregate_capture::MIX_SAMPLE_RATE` mono,
+// since that's what the mixer emits regardless of the two input devices'
+// native rates; the mic and system sources use whatever native rate and
+// channel count the running capture opened.
+fn recorder_slot<'a>(state: &'a AppState, source: &str) -> Result<&'a Arc<Mutex<Option<WavRecorder>>>, String> {
+    match source {
+        "mic" => Ok(&state.mic_recorder),
+        "system" => Ok(&state.system_recorder),
+        "mixed" => Ok(&state.mixed_recorder),
+        other => Err(format!("Unknown recording source '{}' (expected mic, system, or mixed)", other)),
+    }
+}
+
+// Tauri command to start recording the samples already flowing through the
+// mic, system-audio, or mixed stream into a WAV file, independently of the
+// other sources.
+#[tauri::command]
+async fn start_recording(
+    state: tauri::State<'_, AppState>,
+    source: String,
+    path: String,
+) -> Result<(), String> {
+    println!("Starting recording of {} to {}", source, path);
+
+    let (sample_rate, channels) = match source.as_str() {
+        "mic" => {
+            let audio_capture = state.audio_capture.lock().unwrap();
+            let capture = audio_capture.as_ref().ok_or("Mic capture is not running")?;
+            (capture.sample_rate(), capture.channels())
+        }
+        "system" => {
+            let system_audio_capture = state.system_audio_capture.lock().unwrap();
+            let capture = system_audio_capture.as_ref().ok_or("System audio capture is not running")?;
+            (capture.sample_rate(), capture.channels())
+        }
+        "mixed" => (aggregate_capture::MIX_SAMPLE_RATE, 1),
+        other => return Err(format!("Unknown recording source '{}' (expected mic, system, or mixed)", other)),
+    };
+
+    let slot = recorder_slot(&state, &source)?;
+    {
+        let recorder = slot.lock().unwrap();
+        if recorder.is_some() {
+            return Err(format!("Recording already in progress for source '{}'", source));
+        }
+    }
+
+    match WavRecorder::start(std::path::PathBuf::from(path), sample_rate, channels) {
+        Ok(recorder) => {
+            *slot.lock().unwrap() = Some(recorder);
+            println!("Recording started for source '{}'", source);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to start recording: {}", e);
+            Err(format!("Failed to start recording: {}", e))
+        }
+    }
+}
+
+// Tauri command to stop recording a source started with `start_recording`.
+#[tauri::command]
+async fn stop_recording(
+    state: tauri::State<'_, AppState>,
+    source: String,
+) -> Result<(), String> {
+    println!("Stopping recording of {}", source);
+
+    let slot = recorder_slot(&state, &source)?;
+    let recorder = slot.lock().unwrap().take();
+
+    match recorder {
+        Some(recorder) => {
+            recorder.stop()?;
+            println!("Recording stopped for source '{}'", source);
+            Ok(())
+        }
+        None => Err(format!("No recording in progress for source '{}'", source)),
+    }
+}
+
 fn main() {
     // Initialize app state
     let app_state = AppState {
         audio_capture: Arc::new(Mutex::new(None)),
         system_audio_capture: Arc::new(Mutex::new(None)),
+        aggregate_capture: Arc::new(Mutex::new(None)),
+        mic_recorder: Arc::new(Mutex::new(None)),
+        system_recorder: Arc::new(Mutex::new(None)),
+        mixed_recorder: Arc::new(Mutex::new(None)),
     };
 
     tauri::Builder::default()
@@ -148,9 +318,14 @@ fn main() {
             start_system_audio_capture,
             stop_system_audio_capture,
             list_audio_devices,
+            list_audio_hosts,
             start_system_audio_capture_device,
             stop_system_audio_capture_device,
-            list_system_audio_devices
+            list_system_audio_devices,
+            start_aggregate_capture,
+            stop_aggregate_capture,
+            start_recording,
+            stop_recording
         ])
         .setup(|_app| {
             // Only open devtools when explicitly needed
@@ -0,0 +1,98 @@
+/// Converts captured audio to a fixed output rate/format (mono `i16`)
+/// regardless of the source device's native rate or channel count.
+///
+/// Runs sample-by-sample linear interpolation: for output sample `n` the
+/// source position is `pos = n * in_rate / out_rate`; the integer part
+/// indexes the two surrounding input samples and the fractional part
+/// weights between them. The last input sample and the leftover fractional
+/// offset are carried across calls to `process` so consecutive callback
+/// buffers resample as one continuous stream instead of clicking at the
+/// block boundary.
+pub struct Resampler {
+    in_rate: u32,
+    out_rate: u32,
+    pos: f64,
+    last_sample: i16,
+    chunk_size: usize,
+    buffer: Vec<i16>,
+}
+
+/// Length in milliseconds of each emitted chunk at the output rate (320
+/// samples at 16kHz), so consumers get a steady cadence of fixed-size
+/// chunks instead of one emit per callback.
+pub const DEFAULT_CHUNK_MS: u32 = 20;
+
+impl Resampler {
+    pub fn new(in_rate: u32, out_rate: u32) -> Self {
+        let chunk_size = ((out_rate as u64 * DEFAULT_CHUNK_MS as u64) / 1000) as usize;
+        Resampler {
+            in_rate,
+            out_rate,
+            pos: 0.0,
+            last_sample: 0,
+            chunk_size: chunk_size.max(1),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Downmixes `input` (interleaved, `channels` wide) to mono, resamples
+    /// it to `out_rate`, and returns any fixed-size chunks now ready to
+    /// emit. Leftover samples stay buffered for the next call.
+    pub fn process(&mut self, input: &[i16], channels: u16) -> Vec<Vec<i16>> {
+        let mono = downmix_to_mono(input, channels);
+        self.resample_into_buffer(&mono);
+
+        let mut chunks = Vec::new();
+        while self.buffer.len() >= self.chunk_size {
+            chunks.push(self.buffer.drain(..self.chunk_size).collect());
+        }
+        chunks
+    }
+
+    fn resample_into_buffer(&mut self, mono: &[i16]) {
+        if mono.is_empty() {
+            return;
+        }
+
+        let ratio = self.in_rate as f64 / self.out_rate as f64;
+        let mut pos = self.pos;
+
+        while pos < mono.len() as f64 {
+            let i = pos.floor() as isize;
+            let f = pos - i as f64;
+
+            let s0 = if i < 0 { self.last_sample } else { mono[i as usize] };
+            let next_index = i + 1;
+            if next_index >= 0 && next_index as usize >= mono.len() {
+                // Next sample isn't available yet; stop and resume here
+                // once the following callback's data arrives.
+                break;
+            }
+            let s1 = if next_index < 0 { self.last_sample } else { mono[next_index as usize] };
+
+            let out = s0 as f64 * (1.0 - f) + s1 as f64 * f;
+            self.buffer.push(out.round() as i16);
+            pos += ratio;
+        }
+
+        self.pos = pos - mono.len() as f64;
+        self.last_sample = *mono.last().unwrap();
+    }
+}
+
+/// Averages interleaved multi-channel samples down to mono. A no-op for
+/// already-mono input.
+fn downmix_to_mono(input: &[i16], channels: u16) -> Vec<i16> {
+    if channels <= 1 {
+        return input.to_vec();
+    }
+
+    let channels = channels as usize;
+    input
+        .chunks_exact(channels)
+        .map(|frame| {
+            let sum: i32 = frame.iter().map(|&s| s as i32).sum();
+            (sum / channels as i32) as i16
+        })
+        .collect()
+}
@@ -1,44 +1,217 @@
 use tauri::{AppHandle, Emitter};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Host, Stream, StreamConfig, SampleFormat, BufferSize, SampleRate};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use cpal::{StreamConfig, SampleFormat, BufferSize, SampleRate};
+use std::sync::mpsc;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use crate::audio_capture::{AudioDeviceLostEvent, AudioDeviceReconnectedEvent, stop_recorder_on_spec_mismatch};
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use crate::wav_recorder::WavRecorder;
+
+/// How long to wait before rebuilding against the new default device after
+/// a loss, so a flaky unplug/replug doesn't spin the rebuild loop.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SystemAudioDevice {
     pub name: String,
     pub device_type: String, // "system_output" for system audio capture
     pub is_default: bool,
+    pub host: String,
+}
+
+/// Sent from the stream's error callback to the owning capture thread, or
+/// from `stop()` to tear it down.
+#[cfg(target_os = "macos")]
+enum CaptureControl {
+    Stop,
+    DeviceLost(String),
 }
 
+/// Owns the capture thread that keeps the `cpal::Stream` alive, the same
+/// way [`crate::audio_capture::AudioCapture`] does: the stream can't live on
+/// this struct directly since it's `!Send`, so it's parked on a dedicated
+/// thread until told to stop, at which point it drops and capture halts.
+/// That thread also supervises the stream, rebuilding it against the same
+/// device name (and emitting `audio-device-lost`/`audio-device-reconnected`)
+/// if the virtual device disappears and reappears.
 #[cfg(target_os = "macos")]
 pub struct SystemAudioCapture {
-    app_handle: AppHandle,
+    _app_handle: AppHandle,
+    control_tx: mpsc::Sender<CaptureControl>,
+    worker: Option<JoinHandle<()>>,
+    /// The native sample rate the stream currently has open and the channel
+    /// count of what's tee'd into the recorder (always 1, since the callbacks
+    /// downmix to mono), so `start_recording` can build a matching WAV
+    /// header. Shared with the capture thread so a reconnect that lands on a
+    /// different rate is reflected here too.
+    spec: Arc<Mutex<(u32, u16)>>,
 }
 
 #[cfg(target_os = "macos")]
 impl SystemAudioCapture {
-    pub async fn new_with_device(app_handle: AppHandle, device_name: String) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn new_with_device(
+        app_handle: AppHandle,
+        device_name: String,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         println!("Starting system audio capture for device: {}", device_name);
-        
-        let host = cpal::default_host();
-        
+
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        let (control_tx, control_rx) = mpsc::channel::<CaptureControl>();
+        let control_tx_clone = control_tx.clone();
+        let app_handle_clone = app_handle.clone();
+        let spec = Arc::new(Mutex::new((0, 0)));
+        let spec_clone = spec.clone();
+
+        let worker = thread::spawn(move || {
+            Self::run_capture(app_handle_clone, device_name, host_name, recorder, spec_clone, ready_tx, control_tx_clone, control_rx);
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Err("System audio capture thread exited before starting".into()),
+        }
+
+        println!("System audio capture started successfully");
+
+        Ok(SystemAudioCapture {
+            _app_handle: app_handle,
+            control_tx,
+            worker: Some(worker),
+            spec,
+        })
+    }
+
+    /// The native sample rate the stream currently has open. May change
+    /// across a reconnect.
+    pub fn sample_rate(&self) -> u32 {
+        self.spec.lock().unwrap().0
+    }
+
+    /// The channel count of the samples tee'd to the recorder; always 1,
+    /// since the callbacks downmix to mono.
+    pub fn channels(&self) -> u16 {
+        self.spec.lock().unwrap().1
+    }
+
+    /// Runs on the dedicated capture thread: builds and plays the stream,
+    /// signals readiness via `ready_tx`, then waits on `control_rx`. A
+    /// `Stop` (or the sender dropping) ends the loop and drops the stream.
+    /// A `DeviceLost` emits `audio-device-lost` and retries against the
+    /// device name again (virtual audio devices like BlackHole reappear
+    /// under the same name once reinstalled/reconnected), emitting
+    /// `audio-device-reconnected` on success.
+    fn run_capture(
+        app_handle: AppHandle,
+        device_name: String,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+        spec: Arc<Mutex<(u32, u16)>>,
+        ready_tx: mpsc::Sender<Result<(), String>>,
+        control_tx: mpsc::Sender<CaptureControl>,
+        control_rx: mpsc::Receiver<CaptureControl>,
+    ) {
+        let mut first_attempt = true;
+
+        loop {
+            let (stream, sample_rate, channels) = match Self::build_stream(app_handle.clone(), device_name.clone(), host_name.clone(), recorder.clone(), control_tx.clone()) {
+                Ok(parts) => parts,
+                Err(e) => {
+                    if first_attempt {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                    } else {
+                        eprintln!("Failed to rebuild system audio stream after device loss: {}", e);
+                    }
+                    return;
+                }
+            };
+
+            if let Err(e) = stream.play() {
+                if first_attempt {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to restart system audio stream after device loss: {}", e);
+                }
+                return;
+            }
+
+            // Update the shared spec on every successful (re)build — see
+            // the note on `spec` above. A reconnect that lands on a
+            // different rate/channels can't be reconciled with a WAV header
+            // already written for an in-progress recording, so stop it
+            // rather than silently tee'ing mismatched samples.
+            let previous_spec = *spec.lock().unwrap();
+            *spec.lock().unwrap() = (sample_rate, channels);
+            if !first_attempt {
+                stop_recorder_on_spec_mismatch(&app_handle, "system", &recorder, previous_spec, (sample_rate, channels));
+            }
+
+            if first_attempt {
+                let _ = ready_tx.send(Ok(()));
+                first_attempt = false;
+            } else {
+                println!("System audio device reconnected: {}", device_name);
+                if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                    device_name: device_name.clone(),
+                }) {
+                    eprintln!("Failed to emit audio-device-reconnected: {}", e);
+                }
+            }
+
+            match control_rx.recv() {
+                Ok(CaptureControl::Stop) | Err(_) => {
+                    return;
+                }
+                Ok(CaptureControl::DeviceLost(reason)) => {
+                    drop(stream);
+                    eprintln!("System audio device lost: {} ({})", device_name, reason);
+                    if let Err(e) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                        device_name: device_name.clone(),
+                        reason,
+                    }) {
+                        eprintln!("Failed to emit audio-device-lost: {}", e);
+                    }
+
+                    thread::sleep(RECONNECT_BACKOFF);
+                }
+            }
+        }
+    }
+
+    fn build_stream(
+        app_handle: AppHandle,
+        device_name: String,
+        host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+        control_tx: mpsc::Sender<CaptureControl>,
+    ) -> Result<(cpal::Stream, u32, u16), Box<dyn std::error::Error + Send + Sync>> {
+        let host = crate::audio_capture::resolve_host(host_name.as_deref())?;
+
         // Find the system audio device (usually this would be a virtual device like BlackHole)
         let mut selected_device = None;
-        
+
         // Look for system audio devices in input devices (virtual devices appear as inputs)
         for device in host.input_devices()? {
             if let Ok(device_name_str) = device.name() {
-                if device_name_str.contains("BlackHole") || 
-                   device_name_str.contains("Soundflower") || 
+                if device_name_str.contains("BlackHole") ||
+                   device_name_str.contains("Soundflower") ||
                    device_name_str == device_name {
                     selected_device = Some(device);
                     break;
                 }
             }
         }
-        
+
         // If no virtual device found, try to use the specified device as system audio
         if selected_device.is_none() {
             for device in host.output_devices()? {
@@ -55,46 +228,48 @@ impl SystemAudioCapture {
                 }
             }
         }
-        
+
         let device = selected_device.ok_or(format!(
             "System audio device '{}' not found. \
             For system audio capture, you need to install BlackHole or Soundflower virtual audio device.",
             device_name
         ))?;
-        
+
         println!("Using system audio device: {}", device.name()?);
-        
+
         // Get default config
         let default_config = device.default_input_config()?;
         println!("System audio config: {:?}", default_config);
-        
+
         // Configure stream for system audio
         let sample_rate = if default_config.sample_rate().0 >= 16000 {
             default_config.sample_rate()
         } else {
             SampleRate(44100)
         };
-        
+
         let channels = if default_config.channels() >= 2 {
             2 // System audio is usually stereo
         } else {
             default_config.channels()
         };
-        
+
         let stream_config = StreamConfig {
             channels,
             sample_rate,
             buffer_size: BufferSize::Default,
         };
-        
-        println!("Using system audio stream config: channels={}, sample_rate={}", 
+
+        println!("Using system audio stream config: channels={}, sample_rate={}",
                  channels, sample_rate.0);
-        
+
         let app_handle_clone = app_handle.clone();
-        
+
         // Create stream for system audio capture
         let stream = match default_config.sample_format() {
             SampleFormat::F32 => {
+                let control_tx = control_tx.clone();
+                let recorder = recorder.clone();
                 device.build_input_stream(
                     &stream_config,
                     move |data: &[f32], _: &cpal::InputCallbackInfo| {
@@ -112,11 +287,13 @@ impl SystemAudioCapture {
                                 .map(|&sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
                                 .collect()
                         };
-                        
+
+                        tee_to_recorder(&recorder, &mono_data);
+
                         let audio_bytes = mono_data.iter()
                             .flat_map(|&sample| sample.to_le_bytes())
                             .collect::<Vec<u8>>();
-                        
+
                         // Emit system audio data to frontend
                         if let Err(e) = app_handle_clone.emit("system-audio-data", audio_bytes) {
                             eprintln!("Failed to emit system audio data: {}", e);
@@ -124,11 +301,14 @@ impl SystemAudioCapture {
                     },
                     move |err| {
                         eprintln!("System audio stream error: {}", err);
+                        let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
                     },
                     None,
                 )?
             }
             SampleFormat::I16 => {
+                let control_tx = control_tx.clone();
+                let recorder = recorder.clone();
                 device.build_input_stream(
                     &stream_config,
                     move |data: &[i16], _: &cpal::InputCallbackInfo| {
@@ -140,17 +320,20 @@ impl SystemAudioCapture {
                         } else {
                             data.to_vec()
                         };
-                        
+
+                        tee_to_recorder(&recorder, &mono_data);
+
                         let audio_bytes = mono_data.iter()
                             .flat_map(|&sample| sample.to_le_bytes())
                             .collect::<Vec<u8>>();
-                        
+
                         if let Err(e) = app_handle_clone.emit("system-audio-data", audio_bytes) {
                             eprintln!("Failed to emit system audio data: {}", e);
                         }
                     },
                     move |err| {
                         eprintln!("System audio stream error: {}", err);
+                        let _ = control_tx.send(CaptureControl::DeviceLost(err.to_string()));
                     },
                     None,
                 )?
@@ -159,30 +342,30 @@ impl SystemAudioCapture {
                 return Err("Unsupported sample format for system audio".into());
             }
         };
-        
-        // Start the stream
-        stream.play()?;
-        println!("System audio capture started successfully");
-        
-        // Keep the stream alive
-        std::mem::forget(stream);
-        
-        let capture = SystemAudioCapture {
-            app_handle,
-        };
-        
-        Ok(capture)
+
+        // The mono downmix above always yields a single channel, whatever
+        // the source device's channel count was.
+        Ok((stream, sample_rate.0, 1))
     }
-    
-    pub async fn list_system_audio_devices() -> Result<Vec<SystemAudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
-        let host = cpal::default_host();
+
+    pub async fn stop(mut self) {
+        let _ = self.control_tx.send(CaptureControl::Stop);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        println!("System audio capture stopped");
+    }
+
+    pub async fn list_system_audio_devices(host_name: Option<String>) -> Result<Vec<SystemAudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
+        let host = crate::audio_capture::resolve_host(host_name.as_deref())?;
+        let host_label = host.id().name().to_string();
         let mut devices = Vec::new();
-        
+
         // Look for virtual audio devices that can capture system audio
         for device in host.input_devices()? {
             match device.name() {
                 Ok(name) => {
-                    if name.contains("BlackHole") || 
+                    if name.contains("BlackHole") ||
                        name.contains("Soundflower") ||
                        name.contains("VB-Audio") ||
                        name.contains("Loopback") {
@@ -190,41 +373,436 @@ impl SystemAudioCapture {
                             name: name.clone(),
                             device_type: "system_output".to_string(),
                             is_default: false,
+                            host: host_label.clone(),
                         });
                     }
                 }
                 Err(e) => eprintln!("Error getting device name: {}", e),
             }
         }
-        
+
         // If no virtual devices found, provide instructions
         if devices.is_empty() {
             devices.push(SystemAudioDevice {
                 name: "No system audio devices found - Install BlackHole".to_string(),
                 device_type: "instruction".to_string(),
                 is_default: false,
+                host: host_label,
             });
         }
-        
+
         Ok(devices)
     }
 }
 
-// Non-macOS platforms
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "macos")]
+fn tee_to_recorder(recorder: &Mutex<Option<WavRecorder>>, data: &[i16]) {
+    if let Some(rec) = recorder.lock().unwrap().as_ref() {
+        rec.push_samples(data);
+    }
+}
+
+// Windows: no virtual device needed, WASAPI can open the default *render*
+// endpoint in loopback mode and hand us exactly what's playing to the
+// speakers, via an IAudioClient/IAudioCaptureClient pair.
+#[cfg(target_os = "windows")]
+use windows::core::Interface;
+#[cfg(target_os = "windows")]
+use windows::Win32::Devices::Properties::DEVPKEY_Device_FriendlyName;
+#[cfg(target_os = "windows")]
+use windows::Win32::Media::Audio::{
+    eConsole, eRender, IAudioCaptureClient, IAudioClient, IMMDevice, IMMDeviceCollection,
+    IMMDeviceEnumerator, MMDeviceEnumerator, AUDCLNT_BUFFERFLAGS_SILENT,
+    AUDCLNT_E_DEVICE_INVALIDATED, AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_LOOPBACK,
+    DEVICE_STATE_ACTIVE,
+};
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Com::StructuredStorage::PropVariantToStringAlloc;
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED, STGM_READ};
+
+/// Owns the capture thread that holds the COM/WASAPI objects, mirroring the
+/// macOS `cpal::Stream` ownership above: they're `!Send`, so they live on a
+/// dedicated thread that polls for packets until `stop_rx` fires, at which
+/// point the client stops and everything drops.
+#[cfg(target_os = "windows")]
+pub struct SystemAudioCapture {
+    _app_handle: AppHandle,
+    stop_tx: mpsc::Sender<()>,
+    worker: Option<JoinHandle<()>>,
+    /// The native sample rate the stream currently has open and the channel
+    /// count of what's tee'd into the recorder (always
+    /// 1, since packets are downmixed to mono), so `start_recording` can
+    /// build a matching WAV header. Shared with the capture thread so a
+    /// reconnect that lands on a different rate is reflected here too.
+    spec: Arc<Mutex<(u32, u16)>>,
+}
+
+#[cfg(target_os = "windows")]
+impl SystemAudioCapture {
+    pub async fn new_with_device(
+        app_handle: AppHandle,
+        device_name: String,
+        _host_name: Option<String>,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        // WASAPI loopback goes straight through the Windows audio APIs
+        // rather than cpal, so there's no alternate "host" to select here —
+        // the parameter exists for signature parity with the other platforms.
+        println!("Starting WASAPI loopback capture for device: {}", device_name);
+
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let app_handle_clone = app_handle.clone();
+        let spec = Arc::new(Mutex::new((0, 0)));
+        let spec_clone = spec.clone();
+
+        let worker = thread::spawn(move || {
+            Self::run_capture(app_handle_clone, device_name, recorder, spec_clone, ready_tx, stop_rx);
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Err("System audio capture thread exited before starting".into()),
+        }
+
+        println!("WASAPI loopback capture started successfully");
+
+        Ok(SystemAudioCapture {
+            _app_handle: app_handle,
+            stop_tx,
+            worker: Some(worker),
+            spec,
+        })
+    }
+
+    /// The native sample rate the stream currently has open. May change
+    /// across a reconnect.
+    pub fn sample_rate(&self) -> u32 {
+        self.spec.lock().unwrap().0
+    }
+
+    /// The channel count of the samples tee'd to the recorder; always 1,
+    /// since packets are downmixed to mono.
+    pub fn channels(&self) -> u16 {
+        self.spec.lock().unwrap().1
+    }
+
+    /// Polls for packets, builds and plays the stream, signals readiness via
+    /// `ready_tx`, then loops until told to stop. A fatal capture error whose
+    /// code is `AUDCLNT_E_DEVICE_INVALIDATED` (the render endpoint was
+    /// removed or the default device changed) emits `audio-device-lost` and
+    /// rebuilds against `device_name` again (which falls back to whatever is
+    /// the new default render endpoint when `device_name` is empty),
+    /// emitting `audio-device-reconnected` on success. Any other capture
+    /// error is treated as non-recoverable and ends the thread.
+    fn run_capture(
+        app_handle: AppHandle,
+        device_name: String,
+        recorder: Arc<Mutex<Option<WavRecorder>>>,
+        spec: Arc<Mutex<(u32, u16)>>,
+        ready_tx: mpsc::Sender<Result<(), String>>,
+        stop_rx: mpsc::Receiver<()>,
+    ) {
+        let mut first_attempt = true;
+
+        'outer: loop {
+            let (client, capture_client, channels, sample_rate) = match Self::open_loopback(&device_name) {
+                Ok(parts) => parts,
+                Err(e) => {
+                    if first_attempt {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                    } else {
+                        eprintln!("Failed to rebuild WASAPI loopback stream after device loss: {}", e);
+                    }
+                    return;
+                }
+            };
+
+            if let Err(e) = unsafe { client.Start() } {
+                if first_attempt {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                } else {
+                    eprintln!("Failed to restart WASAPI loopback stream after device loss: {}", e);
+                }
+                return;
+            }
+
+            // WASAPI loopback downmixes to mono before emitting, same as the
+            // macOS branch, so the recorded channel count is always 1. A
+            // reconnect that lands on a different rate can't be reconciled
+            // with a WAV header already written for an in-progress
+            // recording, so stop it rather than silently tee'ing mismatched
+            // samples.
+            let previous_spec = *spec.lock().unwrap();
+            *spec.lock().unwrap() = (sample_rate, 1);
+            if !first_attempt {
+                stop_recorder_on_spec_mismatch(&app_handle, "system", &recorder, previous_spec, (sample_rate, 1));
+            }
+
+            if first_attempt {
+                let _ = ready_tx.send(Ok(()));
+                first_attempt = false;
+            } else {
+                println!("WASAPI loopback device reconnected: {}", device_name);
+                if let Err(e) = app_handle.emit("audio-device-reconnected", AudioDeviceReconnectedEvent {
+                    device_name: device_name.clone(),
+                }) {
+                    eprintln!("Failed to emit audio-device-reconnected: {}", e);
+                }
+            }
+
+            // WASAPI has no blocking "wait for stop" primitive here, so poll
+            // for packets with a short sleep between iterations and check
+            // stop_rx with try_recv instead of a blocking recv.
+            while stop_rx.try_recv().is_err() {
+                loop {
+                    match read_next_packet_mono(&capture_client, channels) {
+                        Ok(Some(mono_data)) => {
+                            tee_to_recorder(&recorder, &mono_data);
+
+                            let audio_bytes = mono_data.iter()
+                                .flat_map(|&sample| sample.to_le_bytes())
+                                .collect::<Vec<u8>>();
+
+                            if let Err(e) = app_handle.emit("system-audio-data", audio_bytes) {
+                                eprintln!("Failed to emit system audio data: {}", e);
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            let _ = unsafe { client.Stop() };
+
+                            if e.code() == AUDCLNT_E_DEVICE_INVALIDATED {
+                                eprintln!("WASAPI loopback device lost: {} ({})", device_name, e);
+                                if let Err(emit_err) = app_handle.emit("audio-device-lost", AudioDeviceLostEvent {
+                                    device_name: device_name.clone(),
+                                    reason: e.message().to_string(),
+                                }) {
+                                    eprintln!("Failed to emit audio-device-lost: {}", emit_err);
+                                }
+                                thread::sleep(RECONNECT_BACKOFF);
+                                continue 'outer;
+                            }
+
+                            eprintln!("WASAPI loopback capture error: {}", e);
+                            return;
+                        }
+                    }
+                }
+                thread::sleep(std::time::Duration::from_millis(10));
+            }
+
+            let _ = unsafe { client.Stop() };
+            return;
+        }
+    }
+
+    fn open_loopback(device_name: &str) -> Result<(IAudioClient, IAudioCaptureClient, u16, u32), Box<dyn std::error::Error + Send + Sync>> {
+        open_loopback_device(device_name)
+    }
+
+    pub async fn stop(mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        println!("WASAPI loopback capture stopped");
+    }
+
+    pub async fn list_system_audio_devices(_host_name: Option<String>) -> Result<Vec<SystemAudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+            let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let default_name = enumerator
+                .GetDefaultAudioEndpoint(eRender, eConsole)
+                .ok()
+                .and_then(|d| device_friendly_name(&d).ok());
+
+            let collection: IMMDeviceCollection = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+            let count = collection.GetCount()?;
+
+            let mut devices = Vec::new();
+            for i in 0..count {
+                let device = collection.Item(i)?;
+                match device_friendly_name(&device) {
+                    Ok(name) => {
+                        let is_default = default_name.as_deref() == Some(name.as_str());
+                        devices.push(SystemAudioDevice {
+                            name,
+                            device_type: "system_output".to_string(),
+                            is_default,
+                            host: "wasapi".to_string(),
+                        });
+                    }
+                    Err(e) => eprintln!("Error getting render endpoint name: {}", e),
+                }
+            }
+
+            Ok(devices)
+        }
+    }
+}
+
+/// Opens a render endpoint in WASAPI loopback mode. Shared by
+/// [`SystemAudioCapture`] and the aggregate capture's system-audio source so both paths
+/// through the same device-selection and `IAudioClient::Initialize` setup.
+#[cfg(target_os = "windows")]
+pub(crate) fn open_loopback_device(device_name: &str) -> Result<(IAudioClient, IAudioCaptureClient, u16, u32), Box<dyn std::error::Error + Send + Sync>> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = find_render_device(&enumerator, device_name)?;
+
+        let client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+        let mix_format = client.GetMixFormat()?;
+        let channels = (*mix_format).nChannels;
+        let sample_rate = (*mix_format).nSamplesPerSec;
+        let bits_per_sample = (*mix_format).wBitsPerSample;
+        let format_tag = (*mix_format).wFormatTag;
+
+        // `read_next_packet_mono` reinterprets the captured buffer as
+        // `*const f32`; a shared-mode mix format is conventionally 32-bit
+        // IEEE float (tag 3) or WAVE_FORMAT_EXTENSIBLE (tag 0xFFFE) wrapping
+        // the same, but nothing guarantees it — if it were ever 16-bit PCM
+        // that cast would read twice as many bytes as the buffer holds.
+        // Refuse to open rather than risk an out-of-bounds read.
+        const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+        const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+        if bits_per_sample != 32 || !matches!(format_tag, WAVE_FORMAT_IEEE_FLOAT | WAVE_FORMAT_EXTENSIBLE) {
+            return Err(format!(
+                "Unsupported WASAPI mix format: {}-bit, format tag {:#06x} (expected 32-bit IEEE float)",
+                bits_per_sample, format_tag
+            ).into());
+        }
+
+        client.Initialize(
+            AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_LOOPBACK,
+            0,
+            0,
+            mix_format,
+            None,
+        )?;
+
+        let capture_client: IAudioCaptureClient = client.GetService()?;
+
+        Ok((client, capture_client, channels, sample_rate))
+    }
+}
+
+/// Reads one pending packet from the capture client and downmixes it to
+/// mono `i16`, or `None` if nothing is queued right now.
+#[cfg(target_os = "windows")]
+pub(crate) fn read_next_packet_mono(capture_client: &IAudioCaptureClient, channels: u16) -> windows::core::Result<Option<Vec<i16>>> {
+    let packet_frames = unsafe { capture_client.GetNextPacketSize()? };
+    if packet_frames == 0 {
+        return Ok(None);
+    }
+
+    let mut buffer = std::ptr::null_mut();
+    let mut frames_available = 0u32;
+    let mut flags = 0u32;
+    unsafe {
+        capture_client.GetBuffer(&mut buffer, &mut frames_available, &mut flags, None, None)?;
+    }
+
+    let mono_data = if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 == 0 && frames_available > 0 {
+        // `open_loopback_device` already rejected any mix format that isn't
+        // 32 bits per sample with an IEEE-float or extensible format tag, so
+        // this cast stays within the buffer (an extensible format's subformat
+        // is not checked); downmix to mono i16 the same way the macOS branch
+        // does.
+        let samples = unsafe {
+            std::slice::from_raw_parts(buffer as *const f32, frames_available as usize * channels as usize)
+        };
+        samples
+            .chunks_exact(channels as usize)
+            .map(|frame| {
+                let avg = frame.iter().sum::<f32>() / channels as f32;
+                (avg.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+            })
+            .collect()
+    } else {
+        vec![0i16; frames_available as usize]
+    };
+
+    unsafe {
+        capture_client.ReleaseBuffer(frames_available)?;
+    }
+
+    Ok(Some(mono_data))
+}
+
+/// Finds the render endpoint matching `device_name`, falling back to the
+/// default render endpoint when it's empty or not found — any render
+/// device can be opened in loopback mode, no virtual device required.
+#[cfg(target_os = "windows")]
+unsafe fn find_render_device(enumerator: &IMMDeviceEnumerator, device_name: &str) -> windows::core::Result<IMMDevice> {
+    if !device_name.is_empty() {
+        let collection: IMMDeviceCollection = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+        let count = collection.GetCount()?;
+        for i in 0..count {
+            let device = collection.Item(i)?;
+            if device_friendly_name(&device).unwrap_or_default() == device_name {
+                return Ok(device);
+            }
+        }
+    }
+
+    enumerator.GetDefaultAudioEndpoint(eRender, eConsole)
+}
+
+/// Reads the human-readable endpoint name via the property store.
+#[cfg(target_os = "windows")]
+unsafe fn device_friendly_name(device: &IMMDevice) -> windows::core::Result<String> {
+    let store = device.OpenPropertyStore(STGM_READ)?;
+    let value = store.GetValue(&DEVPKEY_Device_FriendlyName as *const _ as *const _)?;
+    Ok(PropVariantToStringAlloc(&value)?.to_string()?)
+}
+
+#[cfg(target_os = "windows")]
+fn tee_to_recorder(recorder: &Mutex<Option<WavRecorder>>, data: &[i16]) {
+    if let Some(rec) = recorder.lock().unwrap().as_ref() {
+        rec.push_samples(data);
+    }
+}
+
+
+// Neither macOS nor Windows: no native system-audio path available.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
 pub struct SystemAudioCapture;
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
 impl SystemAudioCapture {
-    pub async fn new_with_device(_app_handle: AppHandle, _device_name: String) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        Err("System audio capture is currently only supported on macOS".into())
+    pub async fn new_with_device(
+        _app_handle: AppHandle,
+        _device_name: String,
+        _host_name: Option<String>,
+        _recorder: std::sync::Arc<std::sync::Mutex<Option<crate::wav_recorder::WavRecorder>>>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Err("System audio capture is currently only supported on macOS and Windows".into())
+    }
+
+    pub async fn stop(self) {}
+
+    pub fn sample_rate(&self) -> u32 {
+        0
     }
-    
-    pub async fn list_system_audio_devices() -> Result<Vec<SystemAudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
+
+    pub fn channels(&self) -> u16 {
+        0
+    }
+
+    pub async fn list_system_audio_devices(_host_name: Option<String>) -> Result<Vec<SystemAudioDevice>, Box<dyn std::error::Error + Send + Sync>> {
         Ok(vec![SystemAudioDevice {
             name: "System audio capture not supported on this platform".to_string(),
             device_type: "error".to_string(),
             is_default: false,
+            host: "unsupported".to_string(),
         }])
     }
 }
\ No newline at end of file
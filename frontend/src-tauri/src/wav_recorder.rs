@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+/// Size in bytes of the canonical WAV header (RIFF + fmt + data chunk
+/// headers) written before any sample data; the RIFF and data chunk sizes
+/// inside it are placeholders until `stop` fixes them up.
+const WAV_HEADER_LEN: u32 = 44;
+const BITS_PER_SAMPLE: u16 = 16;
+
+enum RecorderMessage {
+    Samples(Vec<i16>),
+    Stop,
+}
+
+/// Tees captured mono/stereo `i16` samples to a 16-bit PCM WAV file on disk.
+///
+/// The realtime audio callback can't block on file I/O, so [`push_samples`]
+/// only sends the chunk down a channel; a dedicated thread owns the `File`
+/// and does the actual writing, fixing up the RIFF/data chunk sizes once
+/// `stop` is called.
+///
+/// [`push_samples`]: WavRecorder::push_samples
+pub struct WavRecorder {
+    tx: mpsc::Sender<RecorderMessage>,
+    worker: Option<JoinHandle<Result<(), String>>>,
+}
+
+impl WavRecorder {
+    pub fn start(path: PathBuf, sample_rate: u32, channels: u16) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let file = File::create(&path)?;
+        let mut writer = BufWriter::new(file);
+        write_placeholder_header(&mut writer, sample_rate, channels)?;
+
+        let (tx, rx) = mpsc::channel::<RecorderMessage>();
+        let worker = thread::spawn(move || run_writer(writer, rx));
+
+        Ok(WavRecorder {
+            tx,
+            worker: Some(worker),
+        })
+    }
+
+    /// Queues a chunk of samples to be written; never blocks on disk I/O.
+    pub fn push_samples(&self, samples: &[i16]) {
+        let _ = self.tx.send(RecorderMessage::Samples(samples.to_vec()));
+    }
+
+    /// Signals the writer thread to fix up the chunk sizes and close the
+    /// file, then waits for it to finish.
+    pub fn stop(mut self) -> Result<(), String> {
+        let _ = self.tx.send(RecorderMessage::Stop);
+        match self.worker.take() {
+            Some(worker) => worker.join().unwrap_or_else(|_| Err("WAV writer thread panicked".to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn write_placeholder_header(writer: &mut BufWriter<File>, sample_rate: u32, channels: u16) -> io::Result<()> {
+    let block_align = channels * (BITS_PER_SAMPLE / 8);
+    let byte_rate = sample_rate * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&0u32.to_le_bytes())?; // RIFF chunk size, fixed up on stop
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // fmt chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM format tag
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&0u32.to_le_bytes())?; // data chunk size, fixed up on stop
+
+    writer.flush()
+}
+
+fn run_writer(mut writer: BufWriter<File>, rx: mpsc::Receiver<RecorderMessage>) -> Result<(), String> {
+    let mut data_len: u32 = 0;
+
+    while let Ok(message) = rx.recv() {
+        match message {
+            RecorderMessage::Samples(samples) => {
+                for sample in &samples {
+                    if writer.write_all(&sample.to_le_bytes()).is_err() {
+                        return Err("Failed to write WAV sample data".to_string());
+                    }
+                }
+                data_len = data_len.saturating_add((samples.len() * 2) as u32);
+            }
+            RecorderMessage::Stop => break,
+        }
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+    let mut file = writer.into_inner().map_err(|e| e.to_string())?;
+    finalize_header(&mut file, data_len).map_err(|e| e.to_string())
+}
+
+fn finalize_header(file: &mut File, data_len: u32) -> io::Result<()> {
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&(WAV_HEADER_LEN - 8 + data_len).to_le_bytes())?;
+    file.seek(SeekFrom::Start(40))?;
+    file.write_all(&data_len.to_le_bytes())?;
+    file.flush()
+}